@@ -0,0 +1,207 @@
+use std::{
+    fs::{create_dir_all, read, write},
+    io::Result,
+    path::Path,
+};
+
+/// Target chunk sizes, in bytes, used by [`chunk`]
+pub const MIN_CHUNK_SIZE: usize = 2 * 1024;
+pub const AVG_CHUNK_SIZE: usize = 8 * 1024;
+pub const MAX_CHUNK_SIZE: usize = 64 * 1024;
+
+const fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Fixed table of pseudo-random 64-bit values, one per possible byte, used by the gear hash
+const fn build_gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = splitmix64(i as u64 + 1);
+        i += 1;
+    }
+    table
+}
+
+const GEAR: [u64; 256] = build_gear_table();
+
+fn mask_for_bits(bits: u32) -> u64 {
+    (1u64 << bits) - 1
+}
+
+/// Split `data` into content-defined chunks using a gear-based rolling hash (FastCDC)
+///
+/// A chunk boundary is declared when the low bits of the rolling hash are all zero. A
+/// larger mask (more bits, harder to satisfy) is used before `avg_size` is reached and a
+/// smaller mask (fewer bits, easier to satisfy) afterwards, so boundaries are unlikely early
+/// on and increasingly likely past the target, clustering chunk sizes around `avg_size`
+/// instead of drifting. `min_size` and `max_size` are hard bounds: no boundary is considered
+/// below `min_size`, and one is forced at `max_size` regardless of the hash.
+pub fn chunk(data: &[u8], min_size: usize, avg_size: usize, max_size: usize) -> Vec<&[u8]> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    let avg_bits = avg_size.max(2).ilog2();
+    let mask_small = mask_for_bits(avg_bits.saturating_sub(2).max(1));
+    let mask_large = mask_for_bits(avg_bits + 2);
+
+    let mut chunks = Vec::new();
+    let mut chunk_start = 0usize;
+    let mut hash: u64 = 0;
+
+    for pos in 0..data.len() {
+        hash = (hash << 1).wrapping_add(GEAR[data[pos] as usize]);
+        let window = pos + 1 - chunk_start;
+
+        if window < min_size {
+            continue;
+        }
+
+        let mask = if window < avg_size { mask_large } else { mask_small };
+        if hash & mask == 0 || window >= max_size {
+            chunks.push(&data[chunk_start..pos + 1]);
+            chunk_start = pos + 1;
+            hash = 0;
+        }
+    }
+
+    if chunk_start < data.len() {
+        chunks.push(&data[chunk_start..]);
+    }
+
+    chunks
+}
+
+/// Content-addressed, deduplicating store for backup file chunks
+///
+/// Files are split with [`chunk`] and each unique chunk is written once under its BLAKE3
+/// hex digest inside `root/chunks`, so identical chunks shared across files or across
+/// backups taken over time are only ever stored a single time.
+pub struct ChunkStore {
+    root: String,
+}
+
+impl ChunkStore {
+    pub fn new(root: String) -> Self {
+        ChunkStore { root }
+    }
+
+    fn chunks_dir(&self) -> String {
+        format!("{}/chunks", self.root)
+    }
+
+    /// Split `data` into chunks, writing any chunk not already present, and return the
+    /// ordered list of chunk hashes that reconstruct `data` via [`ChunkStore::load_file`]
+    pub fn store_file(&self, data: &[u8]) -> Result<Vec<String>> {
+        create_dir_all(self.chunks_dir())?;
+
+        let mut hashes = Vec::new();
+        for piece in chunk(data, MIN_CHUNK_SIZE, AVG_CHUNK_SIZE, MAX_CHUNK_SIZE) {
+            let hash = blake3::hash(piece).to_hex().to_string();
+            let chunk_path = format!("{}/{}", self.chunks_dir(), hash);
+            if !Path::new(&chunk_path).exists() {
+                write(&chunk_path, piece)?;
+            }
+            hashes.push(hash);
+        }
+
+        Ok(hashes)
+    }
+
+    /// Reassemble a file's bytes from its ordered chunk hashes
+    pub fn load_file(&self, chunk_hashes: &[String]) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        for hash in chunk_hashes {
+            data.extend(read(format!("{}/{}", self.chunks_dir(), hash))?);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random bytes for tests, generated from the gear table's own
+    /// splitmix64 so no external RNG crate is needed
+    fn pseudo_random_bytes(len: usize, seed: u64) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len);
+        let mut state = seed;
+        while bytes.len() < len {
+            state = splitmix64(state);
+            bytes.extend_from_slice(&state.to_le_bytes());
+        }
+        bytes.truncate(len);
+        bytes
+    }
+
+    #[test]
+    fn chunk_boundaries_cluster_around_the_average_size() {
+        let data = pseudo_random_bytes(4 * 1024 * 1024, 1);
+        let pieces = chunk(&data, MIN_CHUNK_SIZE, AVG_CHUNK_SIZE, MAX_CHUNK_SIZE);
+
+        assert!(pieces.len() > 1);
+        let mean_size = data.len() / pieces.len();
+        assert!(
+            mean_size > AVG_CHUNK_SIZE * 2 / 3 && mean_size < AVG_CHUNK_SIZE * 3 / 2,
+            "mean chunk size {} was not within range of target {}",
+            mean_size,
+            AVG_CHUNK_SIZE
+        );
+
+        let forced_max = pieces.iter().filter(|piece| piece.len() >= MAX_CHUNK_SIZE).count();
+        assert!(
+            (forced_max as f64) < pieces.len() as f64 * 0.05,
+            "too many chunks ({forced_max}/{}) were dragged to the forced max size",
+            pieces.len()
+        );
+    }
+
+    #[test]
+    fn chunk_never_splits_below_min_size_or_above_max_size() {
+        let data = pseudo_random_bytes(1024 * 1024, 2);
+        let pieces = chunk(&data, MIN_CHUNK_SIZE, AVG_CHUNK_SIZE, MAX_CHUNK_SIZE);
+
+        let last = pieces.len() - 1;
+        for (i, piece) in pieces.iter().enumerate() {
+            assert!(piece.len() <= MAX_CHUNK_SIZE);
+            if i != last {
+                assert!(piece.len() >= MIN_CHUNK_SIZE);
+            }
+        }
+    }
+
+    #[test]
+    fn chunk_of_empty_data_is_empty() {
+        assert!(chunk(&[], MIN_CHUNK_SIZE, AVG_CHUNK_SIZE, MAX_CHUNK_SIZE).is_empty());
+    }
+
+    #[test]
+    fn store_and_load_file_round_trips_and_dedupes_chunks() {
+        let root = std::env::temp_dir()
+            .join(format!("launcher_minecraft_handler_chunk_store_test_{}", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let _ = std::fs::remove_dir_all(&root);
+
+        let store = ChunkStore::new(root.clone());
+        let data = pseudo_random_bytes(256 * 1024, 3);
+
+        let hashes_a = store.store_file(&data).unwrap();
+        let hashes_b = store.store_file(&data).unwrap();
+        assert_eq!(hashes_a, hashes_b);
+
+        let loaded = store.load_file(&hashes_a).unwrap();
+        assert_eq!(loaded, data);
+
+        let chunk_files = std::fs::read_dir(format!("{}/chunks", root)).unwrap().count();
+        assert_eq!(chunk_files, hashes_a.iter().collect::<std::collections::HashSet<_>>().len());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    fs::{copy as copy_file, create_dir_all, remove_file, File},
+    io::{copy, Error, ErrorKind, Result},
+    path::Path,
+};
+
+/// Authentication method used to connect to a [`Destination::Sftp`] destination
+#[derive(Clone, Debug)]
+pub enum SftpAuth {
+    Password(String),
+    PrivateKey { path: String, passphrase: Option<String> }
+}
+
+/// Where a finished backup archive is ultimately stored
+///
+/// `BackupManager::zip_backup` always builds the archive on the local filesystem first
+/// (the `zip` crate needs a seekable file handle), then hands it to the destination to be
+/// published: a no-op for [`Destination::Local`], or an upload over SSH for [`Destination::Sftp`].
+#[derive(Clone, Debug)]
+pub enum Destination {
+    Local(String),
+    Sftp { host: String, port: u16, user: String, auth: SftpAuth, remote_path: String }
+}
+
+impl Destination {
+    /// Publish the archive at `local_archive_path` (named `archive_name`) to this destination
+    ///
+    /// For [`Destination::Sftp`], the local staging copy is removed once the upload succeeds —
+    /// the destination is the archive's real home, so keeping a local copy around would defeat
+    /// the point of pushing backups off-machine.
+    pub fn upload(&self, local_archive_path: &str, archive_name: &str) -> Result<()> {
+        let Destination::Sftp { host, port, user, auth, remote_path } = self else {
+            return Ok(());
+        };
+
+        let sftp = connect(host, *port, user, auth)?;
+        ensure_remote_dir(&sftp, remote_path)?;
+
+        let remote_file_path = format!("{}/{}", remote_path, archive_name);
+        let mut remote_file = sftp.create(Path::new(&remote_file_path)).map_err(to_io_error)?;
+        let mut local_file = File::open(local_archive_path)?;
+        copy(&mut local_file, &mut remote_file)?;
+        drop(local_file);
+
+        remove_file(local_archive_path)?;
+
+        Ok(())
+    }
+
+    /// Fetch `archive_name` from this destination into a local file at `local_path`
+    pub fn download(&self, archive_name: &str, local_path: &str) -> Result<()> {
+        match self {
+            Destination::Local(path) => {
+                copy_file(format!("{}/{}", path, archive_name), local_path)?;
+                Ok(())
+            }
+            Destination::Sftp { host, port, user, auth, remote_path } => {
+                let sftp = connect(host, *port, user, auth)?;
+                let remote_file_path = format!("{}/{}", remote_path, archive_name);
+                let mut remote_file = sftp.open(Path::new(&remote_file_path)).map_err(to_io_error)?;
+                let mut local_file = File::create(local_path)?;
+                copy(&mut remote_file, &mut local_file)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// List the archive/manifest file names sitting at this destination
+    ///
+    /// Incremental-backup chaining and retention pruning both need to see what's actually
+    /// stored at the destination, not just what's left in a local staging directory — so this
+    /// always reflects the destination itself: a local directory listing for
+    /// [`Destination::Local`], a remote directory listing over SFTP for [`Destination::Sftp`].
+    pub fn list(&self) -> Result<Vec<String>> {
+        match self {
+            Destination::Local(path) => {
+                let mut names = Vec::new();
+                for entry in std::fs::read_dir(path)? {
+                    let entry = entry?;
+                    if entry.path().is_file() {
+                        if let Some(name) = entry.file_name().to_str() {
+                            names.push(name.to_string());
+                        }
+                    }
+                }
+                Ok(names)
+            }
+            Destination::Sftp { host, port, user, auth, remote_path } => {
+                let sftp = connect(host, *port, user, auth)?;
+                let entries = sftp.readdir(Path::new(remote_path)).map_err(to_io_error)?;
+                Ok(entries.into_iter()
+                    .filter(|(_, stat)| stat.is_file())
+                    .filter_map(|(path, _)| path.file_name().and_then(|name| name.to_str()).map(str::to_string))
+                    .collect())
+            }
+        }
+    }
+
+    /// Delete `archive_name` from this destination
+    pub fn remove(&self, archive_name: &str) -> Result<()> {
+        match self {
+            Destination::Local(path) => remove_file(format!("{}/{}", path, archive_name)),
+            Destination::Sftp { host, port, user, auth, remote_path } => {
+                let sftp = connect(host, *port, user, auth)?;
+                let remote_file_path = format!("{}/{}", remote_path, archive_name);
+                sftp.unlink(Path::new(&remote_file_path)).map_err(to_io_error)
+            }
+        }
+    }
+
+    /// Run `f` against a local, seekable copy of `archive_name` at this destination
+    ///
+    /// For [`Destination::Local`] this is the destination's own file — no copy is made. For
+    /// [`Destination::Sftp`] it's downloaded into a scratch file under `scratch_dir`, which is
+    /// removed again once `f` returns (the zip reader needs a real seekable file, and there's
+    /// no reason to leave a second copy of a remote archive sitting on local disk afterwards).
+    pub fn with_local_copy<R>(&self, archive_name: &str, scratch_dir: &str, f: impl FnOnce(&str) -> Result<R>) -> Result<R> {
+        match self {
+            Destination::Local(path) => f(&format!("{}/{}", path, archive_name)),
+            Destination::Sftp { .. } => {
+                create_dir_all(scratch_dir)?;
+                let scratch_path = format!("{}/.fetch-{}", scratch_dir, archive_name);
+                self.download(archive_name, &scratch_path)?;
+                let result = f(&scratch_path);
+                let _ = remove_file(&scratch_path);
+                result
+            }
+        }
+    }
+}
+
+fn connect(host: &str, port: u16, user: &str, auth: &SftpAuth) -> Result<ssh2::Sftp> {
+    let tcp = std::net::TcpStream::connect((host, port))?;
+    let mut session = ssh2::Session::new().map_err(to_io_error)?;
+    session.set_tcp_stream(tcp);
+    session.handshake().map_err(to_io_error)?;
+
+    match auth {
+        SftpAuth::Password(password) => session.userauth_password(user, password).map_err(to_io_error)?,
+        SftpAuth::PrivateKey { path, passphrase } => {
+            session.userauth_pubkey_file(user, None, Path::new(path), passphrase.as_deref()).map_err(to_io_error)?
+        }
+    }
+
+    session.sftp().map_err(to_io_error)
+}
+
+/// Create every missing directory along `remote_path`, ignoring every `mkdir` failure (a
+/// segment that already exists fails too, and so does one that cannot be created)
+fn ensure_remote_dir(sftp: &ssh2::Sftp, remote_path: &str) -> Result<()> {
+    let mut current = if remote_path.starts_with('/') { String::from("/") } else { String::new() };
+    for segment in remote_path.split('/').filter(|segment| !segment.is_empty()) {
+        if !current.is_empty() && !current.ends_with('/') {
+            current.push('/');
+        }
+        current.push_str(segment);
+        let _ = sftp.mkdir(Path::new(&current), 0o755);
+    }
+
+    Ok(())
+}
+
+fn to_io_error(error: ssh2::Error) -> Error {
+    Error::new(ErrorKind::Other, error.to_string())
+}
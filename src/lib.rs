@@ -1,11 +1,54 @@
 use std::{
-    fs::{write, read_dir, File, metadata, remove_file},
+    collections::{HashMap, HashSet},
+    fs::{write, read, read_dir, create_dir_all, remove_file, File, metadata},
     time::SystemTime,
-    io::{Result, copy},
-    path::Path,
+    io::{Result, Error, ErrorKind, Read, Write, copy},
+    path::{Component, Path, PathBuf},
 };
 
-#[derive(Debug, Clone)]
+mod chunk_store;
+pub use chunk_store::{ChunkStore, MIN_CHUNK_SIZE, AVG_CHUNK_SIZE, MAX_CHUNK_SIZE};
+
+mod destination;
+pub use destination::{Destination, SftpAuth};
+
+/// Escape `"` and `\` so a string can be embedded as a JSON string value by the hand-rolled
+/// formatter in [`BackUpData::format_json`]; the companion reader is [`json_unescape`]
+fn json_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Reverse of [`json_escape`]
+fn json_unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                Some('"') => unescaped.push('"'),
+                Some('\\') => unescaped.push('\\'),
+                Some(other) => {
+                    unescaped.push('\\');
+                    unescaped.push(other);
+                }
+                None => unescaped.push('\\'),
+            }
+        } else {
+            unescaped.push(ch);
+        }
+    }
+    unescaped
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Folders {
     Saves,
     Config,
@@ -15,21 +58,107 @@ pub enum Folders {
     Backups
 }
 
+impl Folders {
+    /// Name of the folder on disk, relative to the Minecraft path
+    pub fn path_segment(&self) -> &'static str {
+        match self {
+            Folders::Saves => "saves",
+            Folders::Config => "config",
+            Folders::Screenshots => "screenshots",
+            Folders::Mods => "mods",
+            Folders::Logs => "logs",
+            Folders::Backups => "backups",
+        }
+    }
+
+    /// Parse a folder name back from its `Debug` representation (as stored in `backup_data.json`)
+    fn from_name(name: &str) -> Option<Folders> {
+        match name {
+            "Saves" => Some(Folders::Saves),
+            "Config" => Some(Folders::Config),
+            "Screenshots" => Some(Folders::Screenshots),
+            "Mods" => Some(Folders::Mods),
+            "Logs" => Some(Folders::Logs),
+            "Backups" => Some(Folders::Backups),
+            _ => None,
+        }
+    }
+}
+
+/// Compression method used when writing a backup archive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompressionKind {
+    Stored,
+    Deflate,
+    Zstd
+}
+
+impl CompressionKind {
+    /// Sensible default compression level for this method
+    pub fn default_level(&self) -> i32 {
+        match self {
+            CompressionKind::Stored => 0,
+            CompressionKind::Deflate => 6,
+            CompressionKind::Zstd => 15,
+        }
+    }
+
+    fn method(&self) -> zip::CompressionMethod {
+        match self {
+            CompressionKind::Stored => zip::CompressionMethod::Stored,
+            CompressionKind::Deflate => zip::CompressionMethod::Deflated,
+            CompressionKind::Zstd => zip::CompressionMethod::Zstd,
+        }
+    }
+}
+
+/// How a backup run stores the bytes of the files it covers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageBackend {
+    /// One zip archive per backup run; see [`BackupManager::zip_backup`]
+    Zip,
+    /// Content-addressed, deduplicating [`ChunkStore`]; see [`BackupManager::chunked_backup`]
+    Chunked
+}
+
 #[derive(Clone, Debug)]
 pub struct BackUpOptions {
     pub default_minecraft_path: String,
     pub folder_options: Vec<Folders>,
     pub destination_path: String,
+    pub destination: Destination,
+    pub storage_backend: StorageBackend,
     pub compress: bool,
+    pub compression_kind: CompressionKind,
+    pub compression_level: i32,
     pub excluded_extensions: Vec<String>
 }
 
+/// Record of a single file covered by a backup
+///
+/// For the [`StorageBackend::Zip`] backend, `archive` is the file name of the archive that
+/// actually stores this file's bytes — the current backup when the file is new or changed,
+/// or an earlier backup's archive when the file is unchanged and was only referenced (see
+/// incremental backups on [`BackupManager::zip_backup`]) — and `chunks` is empty. For the
+/// [`StorageBackend::Chunked`] backend it's the other way around: `archive` is empty and
+/// `chunks` holds the ordered [`ChunkStore`] hashes that reassemble this file's bytes.
+#[derive(Debug, Clone)]
+pub struct BackedUpFile {
+    pub relative_path: String,
+    pub size: u64,
+    pub mtime: u64,
+    pub archive: String,
+    pub chunks: Vec<String>
+}
+
 pub struct BackUpData {
     pub options: BackUpOptions,
     pub timestamp: SystemTime,
     pub size_in_bytes: u64,
     pub file_count: u32,
-    pub json_size_in_bytes: u64
+    pub json_size_in_bytes: u64,
+    pub files: Vec<BackedUpFile>,
+    pub deleted_files: Vec<String>
 }
 
 pub struct BackupManager;
@@ -37,29 +166,47 @@ pub struct BackupManager;
 impl BackUpOptions {
     /// Create new backup options with default values
     pub fn new(minecraft_path: String, folder_options: Vec<Folders>, destination_path: String, compress: bool) -> Self {
+        let compression_kind = CompressionKind::Deflate;
         BackUpOptions {
             default_minecraft_path: minecraft_path,
             folder_options,
+            destination: Destination::Local(destination_path.clone()),
             destination_path,
+            storage_backend: StorageBackend::Zip,
             compress,
+            compression_level: compression_kind.default_level(),
+            compression_kind,
             excluded_extensions: Vec::new()
         }
     }
 
+    /// Push the finished archive somewhere other than the local `destination_path`, e.g. an SFTP server
+    pub fn set_destination(&mut self, destination: Destination) {
+        self.destination = destination;
+    }
+
+    /// Switch between one-zip-per-backup and the deduplicating chunk store
+    pub fn set_storage_backend(&mut self, storage_backend: StorageBackend) {
+        self.storage_backend = storage_backend;
+    }
+
+    /// Change the compression method, resetting the level to that method's default
+    pub fn set_compression_kind(&mut self, compression_kind: CompressionKind) {
+        self.compression_level = compression_kind.default_level();
+        self.compression_kind = compression_kind;
+    }
+
+    /// Override the compression level used for `compression_kind`
+    pub fn set_compression_level(&mut self, level: i32) {
+        self.compression_level = level;
+    }
+
     /// Get all paths based on selected folder options
     pub fn get_all_paths(&self) -> Vec<String> {
         let mut paths: Vec<String> = Vec::new();
 
         for folder in &self.folder_options {
-            let path = match folder {
-                Folders::Saves => format!("{}/saves", self.default_minecraft_path),
-                Folders::Config => format!("{}/config", self.default_minecraft_path),
-                Folders::Screenshots => format!("{}/screenshots", self.default_minecraft_path),
-                Folders::Mods => format!("{}/mods", self.default_minecraft_path),
-                Folders::Logs => format!("{}/logs", self.default_minecraft_path),
-                Folders::Backups => format!("{}/backups", self.default_minecraft_path),
-            };
-            paths.push(path);
+            paths.push(format!("{}/{}", self.default_minecraft_path, folder.path_segment()));
         }
 
         paths
@@ -87,33 +234,41 @@ impl BackUpOptions {
         total_size
     }
 
-    /// Get all files from selected folder options and return all files in the folder as a vector of strings
+    /// Get all files from selected folder options, walking every subdirectory recursively,
+    /// and return all files found as a vector of strings
     pub fn get_all_files(&self) -> Vec<String> {
         let folders: Vec<String> = self.get_all_paths();
         let mut files: Vec<String> = Vec::new();
 
         for folder in folders.iter() {
-            if let Ok(entries) = read_dir(folder) {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
-                        if path.is_file() {
-                            if let Some(ext) = path.extension() {
-                                if let Some(ext_str) = ext.to_str() {
-                                    if self.excluded_extensions.contains(&ext_str.to_string()) {
-                                        continue;
-                                    }
+            self.collect_files_recursive(Path::new(folder), &mut files);
+        }
+
+        files
+    }
+
+    /// Recursively walk `dir`, appending every non-excluded file found to `files`
+    fn collect_files_recursive(&self, dir: &Path, files: &mut Vec<String>) {
+        if let Ok(entries) = read_dir(dir) {
+            for entry in entries {
+                if let Ok(entry) = entry {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        self.collect_files_recursive(&path, files);
+                    } else if path.is_file() {
+                        if let Some(ext) = path.extension() {
+                            if let Some(ext_str) = ext.to_str() {
+                                if self.excluded_extensions.contains(&ext_str.to_string()) {
+                                    continue;
                                 }
                             }
-                            let file_path = path.to_string_lossy().to_string();
-                            files.push(file_path);
                         }
+                        let file_path = path.to_string_lossy().to_string();
+                        files.push(file_path);
                     }
                 }
             }
         }
-
-        files
     }
 }
 
@@ -125,7 +280,9 @@ impl BackUpData {
             timestamp: SystemTime::now(),
             size_in_bytes,
             file_count: 0,
-            json_size_in_bytes: 0
+            json_size_in_bytes: 0,
+            files: Vec::new(),
+            deleted_files: Vec::new()
         }
     }
 
@@ -147,24 +304,51 @@ impl BackUpData {
             Err(_) => 0,
         };
 
+        let files_json = self.files.iter()
+            .map(|file| {
+                let chunks_json = file.chunks.iter()
+                    .map(|hash| format!("\"{}\"", json_escape(hash)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    r#"{{"relative_path": "{}", "size": {}, "mtime": {}, "archive": "{}", "chunks": [{}]}}"#,
+                    json_escape(&file.relative_path), file.size, file.mtime, json_escape(&file.archive), chunks_json
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let deleted_files_json = self.deleted_files.iter()
+            .map(|relative_path| format!("\"{}\"", json_escape(relative_path)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
         format!(
             r#"{{
                 "timestamp": {},
                 "size_in_bytes": {},
                 "file_count": {},
+                "files": [{}],
+                "deleted_files": [{}],
                 "options": {{
                     "folder_options": {:?},
                     "destination_path": "{}",
                     "compress": {},
+                    "compression_kind": {:?},
+                    "compression_level": {},
                     "excluded_extensions": {:?}
                 }}
             }}"#,
             timestamp,
             self.size_in_bytes,
             self.file_count,
+            files_json,
+            deleted_files_json,
             self.options.folder_options,
             self.options.destination_path,
             self.options.compress,
+            self.options.compression_kind,
+            self.options.compression_level,
             self.options.excluded_extensions
         )
     }
@@ -176,42 +360,1026 @@ impl BackUpData {
     }
 }
 
+/// Plan for a single backup run: which files must be (re)written into the new archive,
+/// the full set of files the resulting manifest should list, and which files disappeared
+struct IncrementalPlan {
+    to_store: Vec<(String, String)>,
+    files: Vec<BackedUpFile>,
+    deleted_files: Vec<String>
+}
+
+/// A backup archive found at a destination, alongside its parsed manifest if any
+struct ArchiveEntry {
+    name: String,
+    size: u64,
+    manifest: Option<BackupManifest>
+}
+
+/// How many backups [`BackupManager::prune`] should keep in a destination
+#[derive(Debug, Clone, Copy)]
+pub enum RetentionPolicy {
+    /// Keep only the `n` most recently taken backups
+    KeepLast(usize),
+    /// Grandfather-father-son: keep the newest backup taken on each of the last `keep_daily`
+    /// days, each of the last `keep_weekly` weeks, and each of the last `keep_monthly`
+    /// (30-day) months, unioning the three sets together
+    GrandfatherFatherSon { keep_daily: usize, keep_weekly: usize, keep_monthly: usize }
+}
+
+/// What a [`BackupManager::prune`] run removed
+#[derive(Debug, Clone)]
+pub struct PruneResult {
+    pub removed: Vec<String>,
+    pub bytes_reclaimed: u64
+}
+
 impl BackupManager {
-    /// Create a zip backup of the selected folders
-    pub fn zip_backup(options: &BackUpOptions) {
-        let files = options.get_all_files();
-        let zip_path = if options.compress {
-            format!("{}/backup.zip", options.destination_path)
+    /// Create a backup of the selected folders
+    ///
+    /// When `data.options.storage_backend` is [`StorageBackend::Chunked`], this defers to
+    /// [`BackupManager::chunked_backup`] instead of writing a zip archive.
+    ///
+    /// Otherwise, when a previous backup already exists at `data.options.destination`,
+    /// this performs an incremental backup: files whose size matches the previous backup and
+    /// whose modification time is no newer than recorded there are referenced from the
+    /// archive that already stores them instead of
+    /// being rewritten, and files that disappeared since are recorded as deleted so
+    /// [`BackupManager::restore_backup`] can reproduce the exact state. `data.files` and
+    /// `data.deleted_files` are populated with the resulting manifest before it is embedded
+    /// in the archive as `backup_data.json`.
+    pub fn zip_backup(data: &mut BackUpData) -> Result<()> {
+        if data.options.storage_backend == StorageBackend::Chunked {
+            return Self::chunked_backup(data);
+        }
+
+        let options = data.options.clone();
+        let archive_name = Self::archive_file_name(&options, data.timestamp);
+        let zip_path = format!("{}/{}", options.destination_path, archive_name);
+
+        let previous = Self::find_latest_manifest(&options.destination, &options.destination_path);
+        let plan = Self::plan_incremental(&options, previous.as_ref(), &archive_name);
+
+        let file = File::create(&zip_path)?;
+        let mut zip = zip::ZipWriter::new(file);
+
+        let compression_kind = if options.compress { options.compression_kind } else { CompressionKind::Stored };
+        let compression_level = if compression_kind == CompressionKind::Stored {
+            None
         } else {
-            format!("{}/backup", options.destination_path)
+            Some(options.compression_level)
         };
+        let options_var: zip::write::FileOptions<'_, ()> = zip::write::FileOptions::default()
+            .compression_method(compression_kind.method())
+            .compression_level(compression_level);
 
-        let file = File::create(&zip_path).unwrap();
-        let mut zip = zip::ZipWriter::new(file);
+        // Add only the new or changed files; unchanged files stay referenced in their prior archive
+        for (absolute_path, relative_path) in plan.to_store.iter() {
+            zip.start_file(relative_path.as_str(), options_var)?;
+            let mut f = File::open(absolute_path)?;
+            copy(&mut f, &mut zip)?;
+        }
+
+        data.files = plan.files;
+        data.deleted_files = plan.deleted_files;
+        data.file_count = data.files.len() as u32;
 
-        let options_var: zip::write::FileOptions<'_, ()> = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        let json_data = data.format_json();
+        data.json_size_in_bytes = json_data.len() as u64;
+        zip.start_file("backup_data.json", options_var)?;
+        zip.write_all(json_data.as_bytes())?;
 
-        // Add all selected files
-        for file_path in files.iter() {
-            let path = Path::new(file_path);
-            let rel_path = path.strip_prefix(&options.default_minecraft_path)
-                .unwrap_or(path);
-            let rel_path_str = rel_path.to_string_lossy();
-            zip.start_file(rel_path_str, options_var).unwrap();
-            let mut f = File::open(path).unwrap();
-            copy(&mut f, &mut zip).unwrap();
+        zip.finish()?;
+
+        options.destination.upload(&zip_path, &archive_name)?;
+
+        Ok(())
+    }
+
+    /// File name of the archive for a backup run, derived from its timestamp
+    fn archive_file_name(options: &BackUpOptions, timestamp: SystemTime) -> String {
+        let seconds = timestamp.duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        if options.compress {
+            format!("backup_{}.zip", seconds)
+        } else {
+            format!("backup_{}", seconds)
         }
+    }
+
+    /// Create a backup using the content-addressed [`ChunkStore`] instead of one zip per run
+    ///
+    /// Every selected file is split into content-defined chunks and each unique chunk is
+    /// written once under its BLAKE3 hash in `destination_path/chunks`, so identical chunks
+    /// shared across files, or repeated across backup runs, are only ever stored once.
+    /// Because that dedup already makes storing an unchanged file cheap, every run writes
+    /// every selected file's chunks rather than chaining off a previous manifest the way
+    /// [`BackupManager::zip_backup`] does.
+    ///
+    /// The manifest for this run — relative path, size, mtime and ordered chunk hashes per
+    /// file — is written as plain JSON to `destination_path/backup_<timestamp>.json` and
+    /// handed to `data.options.destination` the same way `zip_backup` hands off its archive.
+    /// [`BackupManager::restore_backup`] recognizes this manifest format and reassembles each
+    /// file via [`ChunkStore::load_file`] instead of extracting a zip entry.
+    ///
+    /// Only [`Destination::Local`] is supported: the chunk store itself is never uploaded,
+    /// only the manifest referencing it, so pairing this backend with a remote destination
+    /// would silently strand the actual backup bytes on this machine. That combination is
+    /// rejected up front instead.
+    fn chunked_backup(data: &mut BackUpData) -> Result<()> {
+        let options = data.options.clone();
 
-        // Add the backup_data.json to the zip
-        let json_path = format!("{}/backup_data.json", options.destination_path);
-        if Path::new(&json_path).exists() {
-            zip.start_file("backup_data.json", options_var).unwrap();
-            let mut f = File::open(&json_path).unwrap();
-            copy(&mut f, &mut zip).unwrap();
-            // Borrar el json después de añadirlo al zip
-            remove_file(&json_path).unwrap();
+        if !matches!(options.destination, Destination::Local(_)) {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "StorageBackend::Chunked only supports Destination::Local; its chunk store is never uploaded, so a remote destination would leave the real backup bytes on this machine",
+            ));
         }
 
-        zip.finish().unwrap();
+        let store = ChunkStore::new(options.destination_path.clone());
+
+        let mut files = Vec::new();
+        for absolute_path in options.get_all_files() {
+            let path = Path::new(&absolute_path);
+            let relative_path = path.strip_prefix(&options.default_minecraft_path)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let (size, mtime) = Self::file_size_and_mtime(path);
+
+            let bytes = read(&absolute_path)?;
+            let chunks = store.store_file(&bytes)?;
+
+            files.push(BackedUpFile { relative_path, size, mtime, archive: String::new(), chunks });
+        }
+
+        data.files = files;
+        data.deleted_files = Vec::new();
+        data.file_count = data.files.len() as u32;
+
+        let manifest_name = Self::manifest_file_name(data.timestamp);
+        let manifest_path = format!("{}/{}", options.destination_path, manifest_name);
+        let json_data = data.format_json();
+        data.json_size_in_bytes = json_data.len() as u64;
+        write(&manifest_path, &json_data)?;
+
+        options.destination.upload(&manifest_path, &manifest_name)?;
+
+        Ok(())
+    }
+
+    /// File name of the manifest written by a [`BackupManager::chunked_backup`] run
+    fn manifest_file_name(timestamp: SystemTime) -> String {
+        let seconds = timestamp.duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        format!("backup_{}.json", seconds)
+    }
+
+    /// Find the most recently taken backup at `destination` by reading the
+    /// `backup_data.json` embedded in every archive there and keeping the newest timestamp
+    fn find_latest_manifest(destination: &Destination, scratch_dir: &str) -> Option<BackupManifest> {
+        Self::list_archives(destination, scratch_dir)
+            .into_iter()
+            .filter_map(|archive| archive.manifest)
+            .max_by_key(|manifest| manifest.timestamp)
+    }
+
+    /// List every backup archive sitting at `destination`, reading each one's embedded
+    /// `backup_data.json` manifest where possible
+    ///
+    /// Archives are always listed from `destination` itself rather than a local staging
+    /// directory, so incremental-backup chaining and retention pruning see what's actually
+    /// stored remotely for [`Destination::Sftp`]. `manifest` is `None` for archives that
+    /// can't be read as a zip or raw manifest (e.g. pre-manifest archives); callers that need
+    /// a creation time to reason about a backup should treat those as unknown rather than
+    /// guessing.
+    fn list_archives(destination: &Destination, scratch_dir: &str) -> Vec<ArchiveEntry> {
+        let Ok(names) = destination.list() else {
+            return Vec::new();
+        };
+
+        let mut archives = Vec::new();
+        for name in names {
+            let Ok((size, manifest)) = destination.with_local_copy(&name, scratch_dir, |local_path| {
+                let size = metadata(local_path).map(|data| data.len()).unwrap_or(0);
+                let manifest = Self::parse_manifest_at(local_path);
+                Ok((size, manifest))
+            }) else {
+                continue;
+            };
+
+            archives.push(ArchiveEntry { name, size, manifest });
+        }
+
+        archives
+    }
+
+    /// Parse the `backup_data.json` manifest out of the local file at `local_path`, trying it
+    /// first as a zip archive (the `Zip` storage backend) and falling back to a raw JSON
+    /// manifest (the `Chunked` storage backend)
+    fn parse_manifest_at(local_path: &str) -> Option<BackupManifest> {
+        if let Some(manifest) = File::open(local_path).ok()
+            .and_then(|file| zip::ZipArchive::new(file).ok())
+            .and_then(|mut archive| Self::read_manifest(&mut archive)) {
+            return Some(manifest);
+        }
+
+        read(local_path).ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|contents| BackupManifest::parse(&contents))
+    }
+
+    /// Delete the oldest backups at `destination` until `policy` is satisfied, returning the
+    /// archive names removed and the bytes reclaimed
+    ///
+    /// Only archives with a parseable manifest are candidates for deletion, since there's no
+    /// reliable way to place an unparseable one in time; those are always kept. An archive
+    /// that a kept manifest still references — directly, or transitively through a chain of
+    /// incremental ancestors — is also kept regardless of age, since an incremental backup's
+    /// files may live in any ancestor archive and deleting one out from under it would corrupt
+    /// every backup descending from it. If `policy` would otherwise remove every backup at the
+    /// destination, the single newest one is kept instead so a destination is never left empty.
+    pub fn prune(destination: &Destination, scratch_dir: &str, policy: RetentionPolicy) -> Result<PruneResult> {
+        let archives = Self::list_archives(destination, scratch_dir);
+        let by_name: HashMap<&str, &ArchiveEntry> = archives.iter()
+            .map(|archive| (archive.name.as_str(), archive))
+            .collect();
+
+        let mut datable: Vec<&ArchiveEntry> = archives.iter()
+            .filter(|archive| archive.manifest.is_some())
+            .collect();
+        datable.sort_by_key(|archive| std::cmp::Reverse(archive.manifest.as_ref().unwrap().timestamp));
+
+        let mut keep = Self::names_to_keep(&datable, policy);
+
+        let total_remaining = archives.len() - datable.iter()
+            .filter(|archive| !keep.contains(&archive.name))
+            .count();
+        if total_remaining == 0 {
+            if let Some(newest) = datable.first() {
+                keep.insert(newest.name.clone());
+            }
+        }
+
+        keep = Self::with_referenced_archives(keep, &by_name);
+
+        let mut removed = Vec::new();
+        let mut bytes_reclaimed = 0u64;
+
+        for archive in datable {
+            if keep.contains(&archive.name) {
+                continue;
+            }
+
+            destination.remove(&archive.name)?;
+            bytes_reclaimed += archive.size;
+            removed.push(archive.name.clone());
+        }
+
+        Ok(PruneResult { removed, bytes_reclaimed })
+    }
+
+    /// Expand `keep` to also include every archive transitively referenced by a
+    /// [`BackedUpFile::archive`] in any of the kept archives' manifests, so an incremental
+    /// backup's ancestors are never deleted out from under it
+    fn with_referenced_archives(keep: HashSet<String>, by_name: &HashMap<&str, &ArchiveEntry>) -> HashSet<String> {
+        let mut result = keep.clone();
+        let mut frontier: Vec<String> = keep.into_iter().collect();
+
+        while let Some(name) = frontier.pop() {
+            let Some(manifest) = by_name.get(name.as_str()).and_then(|archive| archive.manifest.as_ref()) else {
+                continue;
+            };
+
+            for file in manifest.files.iter() {
+                if !file.archive.is_empty() && result.insert(file.archive.clone()) {
+                    frontier.push(file.archive.clone());
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Work out which of `datable` (sorted newest first) should survive `policy`
+    fn names_to_keep(datable: &[&ArchiveEntry], policy: RetentionPolicy) -> HashSet<String> {
+        match policy {
+            RetentionPolicy::KeepLast(n) => datable.iter()
+                .take(n)
+                .map(|archive| archive.name.clone())
+                .collect(),
+            RetentionPolicy::GrandfatherFatherSon { keep_daily, keep_weekly, keep_monthly } => {
+                let mut keep = Self::newest_per_bucket(datable, keep_daily, |timestamp| timestamp / 86_400);
+                keep.extend(Self::newest_per_bucket(datable, keep_weekly, |timestamp| timestamp / (86_400 * 7)));
+                keep.extend(Self::newest_per_bucket(datable, keep_monthly, |timestamp| timestamp / (86_400 * 30)));
+                keep
+            }
+        }
+    }
+
+    /// Keep the newest archive in each of the `limit` most recent buckets of `datable`
+    /// (already sorted newest first), where `bucket_of` maps a manifest timestamp to a
+    /// coarser time bucket (e.g. a day or week number)
+    fn newest_per_bucket(datable: &[&ArchiveEntry], limit: usize, bucket_of: impl Fn(u64) -> u64) -> HashSet<String> {
+        let mut seen_buckets = HashSet::new();
+        let mut keep = HashSet::new();
+
+        for archive in datable {
+            if seen_buckets.len() >= limit {
+                break;
+            }
+
+            let bucket = bucket_of(archive.manifest.as_ref().unwrap().timestamp);
+            if seen_buckets.insert(bucket) {
+                keep.insert(archive.name.clone());
+            }
+        }
+
+        keep
+    }
+
+    /// Compare the current set of selected files against the previous manifest to decide
+    /// which files need to be written into the new archive and which can stay referenced
+    fn plan_incremental(options: &BackUpOptions, previous: Option<&BackupManifest>, archive_name: &str) -> IncrementalPlan {
+        let mut previous_by_path: HashMap<&str, &BackedUpFile> = HashMap::new();
+        if let Some(previous) = previous {
+            for file in previous.files.iter() {
+                previous_by_path.insert(file.relative_path.as_str(), file);
+            }
+        }
+
+        let mut to_store = Vec::new();
+        let mut files = Vec::new();
+        let mut current_paths = Vec::new();
+
+        for absolute_path in options.get_all_files() {
+            let path = Path::new(&absolute_path);
+            let relative_path = path.strip_prefix(&options.default_minecraft_path)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let (size, mtime) = Self::file_size_and_mtime(path);
+            current_paths.push(relative_path.clone());
+
+            // A file is unchanged only if its size matches and its mtime hasn't moved forward;
+            // filesystem mtime resolution can disagree, so a newer mtime always counts as changed
+            match previous_by_path.get(relative_path.as_str()) {
+                Some(previous_file) if previous_file.size == size && mtime <= previous_file.mtime => {
+                    files.push(BackedUpFile { relative_path, size, mtime, archive: previous_file.archive.clone(), chunks: Vec::new() });
+                }
+                _ => {
+                    to_store.push((absolute_path, relative_path.clone()));
+                    files.push(BackedUpFile { relative_path, size, mtime, archive: archive_name.to_string(), chunks: Vec::new() });
+                }
+            }
+        }
+
+        let deleted_files = previous
+            .map(|previous| previous.files.iter()
+                .map(|file| file.relative_path.clone())
+                .filter(|relative_path| !current_paths.contains(relative_path))
+                .collect())
+            .unwrap_or_default();
+
+        IncrementalPlan { to_store, files, deleted_files }
+    }
+
+    /// Size in bytes and modification time (as Unix seconds) of a file, defaulting to zero on error
+    fn file_size_and_mtime(path: &Path) -> (u64, u64) {
+        match metadata(path) {
+            Ok(data) => {
+                let mtime = data.modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or(0);
+                (data.len(), mtime)
+            }
+            Err(_) => (0, 0),
+        }
+    }
+
+    /// Unpack a backup created by [`BackupManager::zip_backup`] into `target_minecraft_path`
+    ///
+    /// Reads the `backup_data.json` entry embedded in the archive to find out which
+    /// [`Folders`] were included. When the manifest lists its files (every backup taken
+    /// since incremental backups were introduced does), each file is pulled from whichever
+    /// archive actually stores it — the given one, or an earlier backup sitting alongside it
+    /// in the same directory — so an incremental backup restores to the exact same state as
+    /// a full one. Older, manifest-less archives fall back to extracting every entry directly.
+    /// Pass a non-empty `folders_filter` to restore only a subset of the original folders
+    /// (e.g. just `Saves`) without touching the rest.
+    ///
+    /// `archive_name` is fetched from `destination` into a local, seekable copy under
+    /// `scratch_dir` before reading (a no-op for [`Destination::Local`]; downloaded over SFTP
+    /// for [`Destination::Sftp`]) — restoring from a remote destination has to pull the bytes
+    /// over before a zip reader or chunk store can make sense of them.
+    ///
+    /// The fetched archive may also be the plain-JSON manifest written by
+    /// [`BackupManager::chunked_backup`] — [`StorageBackend::Chunked`] backups aren't zip
+    /// archives at all, so it's tried as one first and, failing that, read directly as a
+    /// manifest whose files are reassembled from the chunk store in the directory holding the
+    /// local copy of that manifest (the destination's own directory for [`Destination::Local`]).
+    pub fn restore_backup(
+        destination: &Destination,
+        archive_name: &str,
+        scratch_dir: &str,
+        target_minecraft_path: &str,
+        folders_filter: Vec<Folders>,
+    ) -> Result<()> {
+        destination.with_local_copy(archive_name, scratch_dir, |local_path| {
+            if let Ok(file) = File::open(local_path) {
+                if let Ok(mut zip) = zip::ZipArchive::new(file) {
+                    let manifest = Self::read_manifest(&mut zip);
+                    let allowed_prefixes = Self::allowed_prefixes(&folders_filter, manifest.as_ref());
+
+                    return match &manifest {
+                        Some(manifest) if !manifest.files.is_empty() => {
+                            Self::restore_from_manifest(&mut zip, destination, scratch_dir, archive_name, manifest, target_minecraft_path, &allowed_prefixes)
+                        }
+                        _ => Self::restore_from_entries(&mut zip, target_minecraft_path, &allowed_prefixes),
+                    };
+                }
+            }
+
+            let contents = std::fs::read_to_string(local_path)?;
+            let manifest = BackupManifest::parse(&contents)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "not a valid zip archive or backup manifest"))?;
+            let allowed_prefixes = Self::allowed_prefixes(&folders_filter, Some(&manifest));
+            Self::restore_from_chunks(&manifest, local_path, target_minecraft_path, &allowed_prefixes)
+        })
+    }
+
+    /// Restore every file a [`BackupManager::chunked_backup`] manifest lists by reassembling
+    /// it from the [`ChunkStore`] rooted next to `manifest_path`
+    ///
+    /// A chunked backup's chunk files are never uploaded anywhere — only the manifest is — so
+    /// this only ever works when `manifest_path` points at the manifest's original location on
+    /// the local filesystem (i.e. a [`Destination::Local`] backup); fetching a chunked
+    /// manifest from [`Destination::Sftp`] leaves its chunk store unreachable.
+    fn restore_from_chunks(
+        manifest: &BackupManifest,
+        manifest_path: &str,
+        target_minecraft_path: &str,
+        allowed_prefixes: &Option<Vec<String>>,
+    ) -> Result<()> {
+        let store_root = Path::new(manifest_path).parent().unwrap_or_else(|| Path::new("."));
+        let store = ChunkStore::new(store_root.to_string_lossy().to_string());
+
+        for file in manifest.files.iter() {
+            if let Some(prefixes) = allowed_prefixes {
+                if !prefixes.iter().any(|prefix| file.relative_path.starts_with(prefix.as_str())) {
+                    continue;
+                }
+            }
+
+            let Some(sanitized) = Self::sanitize_relative_path(&file.relative_path) else { continue };
+            let target_path = Path::new(target_minecraft_path).join(sanitized);
+            if let Some(parent) = target_path.parent() {
+                create_dir_all(parent)?;
+            }
+
+            let bytes = store.load_file(&file.chunks)?;
+            write(target_path, bytes)?;
+        }
+
+        Self::apply_deletions(manifest, target_minecraft_path, allowed_prefixes)
+    }
+
+    /// Restore every entry in `zip` directly, skipping the embedded manifest itself
+    ///
+    /// Used for archives that predate per-file manifests, or whose manifest failed to parse.
+    fn restore_from_entries(zip: &mut zip::ZipArchive<File>, target_minecraft_path: &str, allowed_prefixes: &Option<Vec<String>>) -> Result<()> {
+        for i in 0..zip.len() {
+            let mut entry = zip.by_index(i)?;
+            let name = entry.name().to_string();
+            if name == "backup_data.json" {
+                continue;
+            }
+            if let Some(prefixes) = allowed_prefixes {
+                if !prefixes.iter().any(|prefix| name.starts_with(prefix.as_str())) {
+                    continue;
+                }
+            }
+
+            let Some(sanitized) = Self::sanitize_relative_path(&name) else { continue };
+            let target_path = Path::new(target_minecraft_path).join(sanitized);
+            if let Some(parent) = target_path.parent() {
+                create_dir_all(parent)?;
+            }
+            let mut out_file = File::create(target_path)?;
+            copy(&mut entry, &mut out_file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Restore every file the manifest lists, reading each one from the archive that
+    /// actually stores it — `current_zip` for files stored in `current_archive_name`, or a
+    /// sibling archive fetched from `destination` for files only referenced
+    fn restore_from_manifest(
+        current_zip: &mut zip::ZipArchive<File>,
+        destination: &Destination,
+        scratch_dir: &str,
+        current_archive_name: &str,
+        manifest: &BackupManifest,
+        target_minecraft_path: &str,
+        allowed_prefixes: &Option<Vec<String>>,
+    ) -> Result<()> {
+        for file in manifest.files.iter() {
+            if let Some(prefixes) = allowed_prefixes {
+                if !prefixes.iter().any(|prefix| file.relative_path.starts_with(prefix.as_str())) {
+                    continue;
+                }
+            }
+
+            let Some(sanitized) = Self::sanitize_relative_path(&file.relative_path) else { continue };
+            let target_path = Path::new(target_minecraft_path).join(sanitized);
+            if let Some(parent) = target_path.parent() {
+                create_dir_all(parent)?;
+            }
+            let mut out_file = File::create(target_path)?;
+
+            if file.archive == current_archive_name {
+                let mut entry = current_zip.by_name(&file.relative_path)?;
+                copy(&mut entry, &mut out_file)?;
+            } else {
+                destination.with_local_copy(&file.archive, scratch_dir, |referenced_local_path| {
+                    let referenced_file = File::open(referenced_local_path)?;
+                    let mut referenced_zip = zip::ZipArchive::new(referenced_file)?;
+                    let mut entry = referenced_zip.by_name(&file.relative_path)?;
+                    copy(&mut entry, &mut out_file)?;
+                    Ok(())
+                })?;
+            }
+        }
+
+        Self::apply_deletions(manifest, target_minecraft_path, allowed_prefixes)
+    }
+
+    /// Remove files the manifest recorded as deleted since the previous incremental backup,
+    /// so restoring a later backup reproduces its exact state instead of leaving behind files
+    /// an earlier backup restored that no longer belong
+    fn apply_deletions(manifest: &BackupManifest, target_minecraft_path: &str, allowed_prefixes: &Option<Vec<String>>) -> Result<()> {
+        for relative_path in manifest.deleted_files.iter() {
+            if let Some(prefixes) = allowed_prefixes {
+                if !prefixes.iter().any(|prefix| relative_path.starts_with(prefix.as_str())) {
+                    continue;
+                }
+            }
+
+            let Some(sanitized) = Self::sanitize_relative_path(relative_path) else { continue };
+            let target_path = Path::new(target_minecraft_path).join(sanitized);
+            if target_path.exists() {
+                remove_file(target_path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read and parse the embedded `backup_data.json` manifest, if present
+    fn read_manifest(zip: &mut zip::ZipArchive<File>) -> Option<BackupManifest> {
+        let mut manifest_file = zip.by_name("backup_data.json").ok()?;
+        let mut contents = String::new();
+        manifest_file.read_to_string(&mut contents).ok()?;
+        BackupManifest::parse(&contents)
+    }
+
+    /// Work out which relative-path prefixes should be extracted
+    ///
+    /// `folders_filter` takes priority when non-empty; otherwise every folder recorded in
+    /// the manifest is restored. Returns `None` when there is no information to filter on,
+    /// meaning every entry should be extracted.
+    fn allowed_prefixes(folders_filter: &[Folders], manifest: Option<&BackupManifest>) -> Option<Vec<String>> {
+        let folders: Vec<&Folders> = if !folders_filter.is_empty() {
+            folders_filter.iter().collect()
+        } else {
+            manifest?.folder_options.iter().collect()
+        };
+
+        Some(folders.iter().map(|folder| format!("{}/", folder.path_segment())).collect())
+    }
+
+    /// Turn a zip entry name or manifest `relative_path` into a path safe to join onto a
+    /// target directory, rejecting anything that could escape it (a leading `/`, a `..`
+    /// component) — an archive or manifest isn't necessarily trustworthy, since it may have
+    /// come from a remote destination or been corrupted
+    fn sanitize_relative_path(name: &str) -> Option<PathBuf> {
+        let mut sanitized = PathBuf::new();
+
+        for component in Path::new(name).components() {
+            match component {
+                Component::Normal(part) => sanitized.push(part),
+                Component::CurDir => {}
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+            }
+        }
+
+        if sanitized.as_os_str().is_empty() {
+            None
+        } else {
+            Some(sanitized)
+        }
+    }
+
+    /// Pull a single world out of a backup's `saves/` tree and write it as a standalone,
+    /// directly-playable world folder under `target_saves_dir`
+    ///
+    /// `archive_name` is fetched from `destination` into a local, seekable copy under
+    /// `scratch_dir` first, the same way [`BackupManager::restore_backup`] does.
+    ///
+    /// Reads the embedded `backup_data.json` manifest to find every file under
+    /// `saves/{world_name}/`, resolving each one from whichever archive actually stores it
+    /// (see [`BackupManager::restore_backup`] for why that can differ from `archive_name` on
+    /// an incremental backup). Falls back to extracting matching entries directly from the
+    /// fetched archive for manifest-less archives. Unlike a full restore, the result has none
+    /// of the wrapping `saves/{world_name}/` structure: `target_saves_dir/world_name` becomes
+    /// a normal single-player world folder (`level.dat`, `region/`, `playerdata/`, ...) ready
+    /// to be opened in a vanilla client.
+    pub fn export_world(destination: &Destination, archive_name: &str, scratch_dir: &str, world_name: &str, target_saves_dir: &str) -> Result<()> {
+        destination.with_local_copy(archive_name, scratch_dir, |local_path| {
+            let file = File::open(local_path)?;
+            let mut zip = zip::ZipArchive::new(file)?;
+
+            let manifest = Self::read_manifest(&mut zip);
+            let prefix = format!("{}/{}/", Folders::Saves.path_segment(), world_name);
+            let target_world_dir = Path::new(target_saves_dir).join(world_name);
+
+            match &manifest {
+                Some(manifest) if !manifest.files.is_empty() => {
+                    Self::export_world_from_manifest(&mut zip, destination, scratch_dir, archive_name, manifest, &prefix, &target_world_dir)
+                }
+                _ => Self::export_world_from_entries(&mut zip, &prefix, &target_world_dir),
+            }
+        })
+    }
+
+    /// Extract every entry under `prefix` directly from `zip`, stripping `prefix` so the
+    /// result is rooted at `target_world_dir` instead of the original `saves/{world_name}/` path
+    fn export_world_from_entries(zip: &mut zip::ZipArchive<File>, prefix: &str, target_world_dir: &Path) -> Result<()> {
+        for i in 0..zip.len() {
+            let mut entry = zip.by_index(i)?;
+            let name = entry.name().to_string();
+            let relative = match name.strip_prefix(prefix) {
+                Some(relative) if !relative.is_empty() => relative,
+                _ => continue,
+            };
+            let Some(sanitized) = Self::sanitize_relative_path(relative) else { continue };
+
+            let target_path = target_world_dir.join(sanitized);
+            if let Some(parent) = target_path.parent() {
+                create_dir_all(parent)?;
+            }
+            let mut out_file = File::create(target_path)?;
+            copy(&mut entry, &mut out_file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Extract every manifest file under `prefix`, reading each one from the archive that
+    /// actually stores it (`current_archive_name`, or a sibling incremental archive fetched
+    /// from `destination`), and write it under `target_world_dir` with `prefix` stripped from
+    /// its relative path
+    fn export_world_from_manifest(
+        current_zip: &mut zip::ZipArchive<File>,
+        destination: &Destination,
+        scratch_dir: &str,
+        current_archive_name: &str,
+        manifest: &BackupManifest,
+        prefix: &str,
+        target_world_dir: &Path,
+    ) -> Result<()> {
+        for file in manifest.files.iter() {
+            let relative = match file.relative_path.strip_prefix(prefix) {
+                Some(relative) if !relative.is_empty() => relative,
+                _ => continue,
+            };
+            let Some(sanitized) = Self::sanitize_relative_path(relative) else { continue };
+
+            let target_path = target_world_dir.join(sanitized);
+            if let Some(parent) = target_path.parent() {
+                create_dir_all(parent)?;
+            }
+            let mut out_file = File::create(&target_path)?;
+
+            if file.archive == current_archive_name {
+                let mut entry = current_zip.by_name(&file.relative_path)?;
+                copy(&mut entry, &mut out_file)?;
+            } else {
+                destination.with_local_copy(&file.archive, scratch_dir, |referenced_local_path| {
+                    let referenced_file = File::open(referenced_local_path)?;
+                    let mut referenced_zip = zip::ZipArchive::new(referenced_file)?;
+                    let mut entry = referenced_zip.by_name(&file.relative_path)?;
+                    copy(&mut entry, &mut out_file)?;
+                    Ok(())
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Minimal view of a parsed `backup_data.json`, enough to drive a restore or chain an
+/// incremental backup off the previous one
+struct BackupManifest {
+    timestamp: u64,
+    folder_options: Vec<Folders>,
+    files: Vec<BackedUpFile>,
+    deleted_files: Vec<String>,
+}
+
+impl BackupManifest {
+    /// Parse the hand-written JSON produced by [`BackUpData::format_json`]
+    fn parse(json: &str) -> Option<Self> {
+        let timestamp = Self::extract_field(json, "timestamp")?.parse().ok()?;
+        let folder_options = Self::parse_folder_options(json)?;
+        let files = Self::parse_files(json).unwrap_or_default();
+        let deleted_files = Self::parse_string_list(json, "deleted_files").unwrap_or_default();
+
+        Some(BackupManifest { timestamp, folder_options, files, deleted_files })
+    }
+
+    fn parse_folder_options(json: &str) -> Option<Vec<Folders>> {
+        let marker = "\"folder_options\": [";
+        let start = json.find(marker)? + marker.len();
+        let end = start + json[start..].find(']')?;
+        Some(
+            json[start..end]
+                .split(',')
+                .map(|name| name.trim())
+                .filter(|name| !name.is_empty())
+                .filter_map(Folders::from_name)
+                .collect()
+        )
+    }
+
+    /// Parse the `"files": [{...}, {...}]` array into [`BackedUpFile`] records
+    ///
+    /// Each file object may itself contain a nested `"chunks": [...]` array, so the end of
+    /// the outer array can't be found with a naive first-`]` search; [`Self::matching_bracket`]
+    /// tracks nesting depth instead. A record that fails to parse (e.g. a future format change
+    /// drops a field) is skipped on its own rather than discarding every other file in the
+    /// manifest — callers like [`BackupManager::prune`] rely on `files` being as complete as
+    /// the manifest allows.
+    fn parse_files(json: &str) -> Option<Vec<BackedUpFile>> {
+        let marker = "\"files\": [";
+        let open = json.find(marker)? + marker.len() - 1;
+        let end = Self::matching_bracket(json, open)?;
+        let mut files = Vec::new();
+
+        let mut pos = open + 1;
+        while let Some(relative_open) = json[pos..end].find('{') {
+            let object_start = pos + relative_open;
+            let Some(object_end) = Self::matching_bracket(json, object_start) else { break };
+            let object = &json[object_start + 1..object_end];
+
+            if let Some(file) = Self::parse_file_object(object) {
+                files.push(file);
+            }
+
+            pos = object_end + 1;
+        }
+
+        Some(files)
+    }
+
+    fn parse_file_object(object: &str) -> Option<BackedUpFile> {
+        Some(BackedUpFile {
+            relative_path: Self::extract_field(object, "relative_path")?,
+            size: Self::extract_field(object, "size")?.parse().ok()?,
+            mtime: Self::extract_field(object, "mtime")?.parse().ok()?,
+            archive: Self::extract_field(object, "archive")?,
+            chunks: Self::parse_string_list(object, "chunks").unwrap_or_default(),
+        })
+    }
+
+    /// Find the closing `]`/`}` matching the opening one at `open_pos`, skipping over the
+    /// contents of quoted strings (respecting `\`-escapes) so that a literal bracket, brace,
+    /// or quote inside a file name can't be mistaken for JSON structure
+    fn matching_bracket(json: &str, open_pos: usize) -> Option<usize> {
+        let bytes = json.as_bytes();
+        let open = bytes[open_pos];
+        let close = if open == b'[' { b']' } else { b'}' };
+        let mut depth = 0i32;
+        let mut i = open_pos;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b'"' => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != b'"' {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                }
+                byte if byte == open => depth += 1,
+                byte if byte == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        None
+    }
+
+    /// Find the unescaped closing `"` matching the opening one at `open_pos`
+    fn matching_quote(json: &str, open_pos: usize) -> Option<usize> {
+        let bytes = json.as_bytes();
+        let mut i = open_pos + 1;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => return Some(i),
+                _ => i += 1,
+            }
+        }
+
+        None
+    }
+
+    /// Parse a `"key": ["a", "b"]` array of strings
+    fn parse_string_list(json: &str, key: &str) -> Option<Vec<String>> {
+        let marker = format!("\"{}\": [", key);
+        let open = json.find(&marker)? + marker.len() - 1;
+        let end = Self::matching_bracket(json, open)?;
+        let mut values = Vec::new();
+
+        let mut i = open + 1;
+        while i < end {
+            if json.as_bytes()[i] == b'"' {
+                let close = Self::matching_quote(json, i)?;
+                values.push(json_unescape(&json[i + 1..close]));
+                i = close + 1;
+            } else {
+                i += 1;
+            }
+        }
+
+        Some(values)
+    }
+
+    /// Extract the value of `"key": value` from a flat JSON object, handling both quoted
+    /// strings (unescaping them) and bare numbers
+    fn extract_field(json: &str, key: &str) -> Option<String> {
+        let marker = format!("\"{}\": ", key);
+        let start = json.find(&marker)? + marker.len();
+
+        if json.as_bytes().get(start) == Some(&b'"') {
+            let end = Self::matching_quote(json, start)?;
+            Some(json_unescape(&json[start + 1..end]))
+        } else {
+            let rest = &json[start..];
+            let end = rest.find(|character: char| character == ',' || character == '}')?;
+            Some(rest[..end].trim().to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn test_dir(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("launcher_minecraft_handler_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn options_for(root: &str) -> BackUpOptions {
+        BackUpOptions::new(root.to_string(), vec![Folders::Saves], format!("{}/backups", root), false)
+    }
+
+    #[test]
+    fn plan_incremental_references_unchanged_file_instead_of_storing_it_again() {
+        let root = test_dir("unchanged");
+        fs::create_dir_all(format!("{}/saves", root)).unwrap();
+        let world_path = format!("{}/saves/world.dat", root);
+        fs::write(&world_path, b"hello").unwrap();
+
+        let (size, mtime) = BackupManager::file_size_and_mtime(Path::new(&world_path));
+        let previous = BackupManifest {
+            timestamp: 0,
+            folder_options: vec![Folders::Saves],
+            files: vec![BackedUpFile {
+                relative_path: "saves/world.dat".to_string(),
+                size,
+                mtime,
+                archive: "backup_1.zip".to_string(),
+                chunks: Vec::new(),
+            }],
+            deleted_files: Vec::new(),
+        };
+
+        let plan = BackupManager::plan_incremental(&options_for(&root), Some(&previous), "backup_2.zip");
+
+        assert!(plan.to_store.is_empty());
+        assert_eq!(plan.files.len(), 1);
+        assert_eq!(plan.files[0].archive, "backup_1.zip");
+        assert!(plan.deleted_files.is_empty());
+    }
+
+    #[test]
+    fn plan_incremental_stores_changed_file_and_tracks_deletion() {
+        let root = test_dir("changed");
+        fs::create_dir_all(format!("{}/saves", root)).unwrap();
+        fs::write(format!("{}/saves/world.dat", root), b"hello again, now longer").unwrap();
+
+        let previous = BackupManifest {
+            timestamp: 0,
+            folder_options: vec![Folders::Saves],
+            files: vec![
+                BackedUpFile { relative_path: "saves/world.dat".to_string(), size: 1, mtime: 0, archive: "backup_1.zip".to_string(), chunks: Vec::new() },
+                BackedUpFile { relative_path: "saves/old_region.dat".to_string(), size: 1, mtime: 0, archive: "backup_1.zip".to_string(), chunks: Vec::new() },
+            ],
+            deleted_files: Vec::new(),
+        };
+
+        let plan = BackupManager::plan_incremental(&options_for(&root), Some(&previous), "backup_2.zip");
+
+        assert_eq!(plan.to_store.len(), 1);
+        let world_file = plan.files.iter().find(|file| file.relative_path == "saves/world.dat").unwrap();
+        assert_eq!(world_file.archive, "backup_2.zip");
+        assert_eq!(plan.deleted_files, vec!["saves/old_region.dat".to_string()]);
+    }
+
+    #[test]
+    fn apply_deletions_removes_recorded_files_within_target() {
+        let root = test_dir("apply_deletions");
+        fs::create_dir_all(format!("{}/saves", root)).unwrap();
+        let stale_path = format!("{}/saves/old_region.dat", root);
+        fs::write(&stale_path, b"stale").unwrap();
+
+        let manifest = BackupManifest {
+            timestamp: 0,
+            folder_options: vec![Folders::Saves],
+            files: Vec::new(),
+            deleted_files: vec!["saves/old_region.dat".to_string(), "saves/never_existed.dat".to_string()],
+        };
+
+        BackupManager::apply_deletions(&manifest, &root, &None).unwrap();
+
+        assert!(!Path::new(&stale_path).exists());
+    }
+
+    #[test]
+    fn apply_deletions_ignores_files_outside_the_folder_filter() {
+        let root = test_dir("apply_deletions_filtered");
+        fs::create_dir_all(format!("{}/config", root)).unwrap();
+        let config_path = format!("{}/config/old.cfg", root);
+        fs::write(&config_path, b"stale").unwrap();
+
+        let manifest = BackupManifest {
+            timestamp: 0,
+            folder_options: vec![Folders::Config],
+            files: Vec::new(),
+            deleted_files: vec!["config/old.cfg".to_string()],
+        };
+
+        let allowed_prefixes = Some(vec!["saves/".to_string()]);
+        BackupManager::apply_deletions(&manifest, &root, &allowed_prefixes).unwrap();
+
+        assert!(Path::new(&config_path).exists());
+    }
+
+    #[test]
+    fn manifest_parse_round_trips_quotes_and_braces_in_file_names() {
+        let tricky_name = "saves/a \"{world}\" copy.dat";
+        let json = format!(
+            r#"{{"timestamp": 123, "folder_options": [Saves], "files": [{{"relative_path": "{}", "size": 10, "mtime": 20, "archive": "backup_1.zip", "chunks": ["{}"]}}], "deleted_files": ["{}"]}}"#,
+            json_escape(tricky_name),
+            json_escape("chunk-{hash}"),
+            json_escape(tricky_name)
+        );
+
+        let manifest = BackupManifest::parse(&json).expect("manifest should parse");
+
+        assert_eq!(manifest.timestamp, 123);
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.files[0].relative_path, tricky_name);
+        assert_eq!(manifest.files[0].chunks, vec!["chunk-{hash}".to_string()]);
+        assert_eq!(manifest.deleted_files, vec![tricky_name.to_string()]);
+    }
+
+    #[test]
+    fn manifest_parse_skips_only_the_malformed_record() {
+        let json = r#"{"timestamp": 1, "folder_options": [Saves], "files": [{"relative_path": "saves/good.dat", "size": 1, "mtime": 2, "archive": "backup_1.zip", "chunks": []}, {"relative_path": "saves/missing_size.dat", "mtime": 2, "archive": "backup_1.zip", "chunks": []}], "deleted_files": []}"#;
+
+        let manifest = BackupManifest::parse(json).expect("manifest should parse");
+
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.files[0].relative_path, "saves/good.dat");
     }
 }
\ No newline at end of file